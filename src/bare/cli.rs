@@ -1,19 +1,18 @@
-//! CLI facilities. Provides an argument parser in the form of [`Args`],
-//! as well as some UI utilities.
+//! CLI facilities. Provides an argument parser in the form of
+//! [`CliArgs`]/[`TypedCliArgs`], as well as some UI utilities.
 //!
-//! [`Args`]: ./struct.Args.html
+//! [`CliArgs`]: ./struct.CliArgs.html
+//! [`TypedCliArgs`]: ./struct.TypedCliArgs.html
 use crate::bare::{
-    exit::{self, ExitCode},
-    log::{RainbowLog, Writer},
-    Pattern
+    error::{Error, Result},
+    log::RainbowLog,
+    Pattern,
 };
 use regex;
 use regex::Regex;
-use std::env;
 use std::io;
-use std::io::Write;
-use std::path::PathBuf;
-use term::color;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
 
 trait RegexUtils {
     fn named(self, name: &str) -> Self;
@@ -21,7 +20,7 @@ trait RegexUtils {
     fn case_insensitive(self) -> Self;
 }
 
-impl RegexUtils for Result<Regex, regex::Error> {
+impl RegexUtils for std::result::Result<Regex, regex::Error> {
     fn named(self, name: &str) -> Self {
         match self {
             Ok(regex) => Regex::new(&format!("(?P<{}>({}))", name, regex)),
@@ -37,307 +36,439 @@ impl RegexUtils for Result<Regex, regex::Error> {
     }
 }
 
+/// The syntax used to interpret the body of a `-p`/`--pattern` argument,
+/// selected via a `syntax:` prefix (e.g. `glob:*.jpeg`). Mirrors the
+/// `PatternSyntax` Mercurial uses for its `.hgignore`/`-I`/`-X` patterns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PatternSyntax {
+    /// `re:`   - the body is used as-is, as a regular expression.
+    Regex,
+    /// `glob:` - the body is a shell glob, translated to a regex. Default.
+    Glob,
+    /// `lit:`  - the body is matched literally against the whole file name.
+    Literal,
+}
 
+impl PatternSyntax {
+    /// Split a raw pattern into its `syntax:` prefix (if recognized) and
+    /// the remaining body, defaulting to `Glob` when no prefix is present.
+    pub(crate) fn parse(raw: &str) -> (Self, &str) {
+        if let Some(body) = raw.strip_prefix("re:") {
+            (PatternSyntax::Regex, body)
+        } else if let Some(body) = raw.strip_prefix("glob:") {
+            (PatternSyntax::Glob, body)
+        } else if let Some(body) = raw.strip_prefix("lit:") {
+            (PatternSyntax::Literal, body)
+        } else {
+            (PatternSyntax::Glob, raw)
+        }
+    }
+}
 
+/// Bytes that are regex metacharacters and thus need escaping when a
+/// glob or literal pattern is translated into a regex.
+const GLOB_METACHARACTERS: &[u8] = b"()[]{}?*+-|^$\\.&~#";
 
-trait ArgsFor {
-    fn args_for(&self, aliases: &[&str]) -> Option<Vec<String>>;
+/// Escape a single character for inclusion in a regex, per [`GLOB_METACHARACTERS`].
+fn escape_glob_char(ch: char, out: &mut String) {
+    if ch.is_ascii() && (GLOB_METACHARACTERS.contains(&(ch as u8)) || ch.is_whitespace()) {
+        out.push('\\');
+    }
+    out.push(ch);
 }
 
-impl ArgsFor for [String] {
-    fn args_for(&self, aliases: &[&str]) -> Option<Vec<String>> {
-        let is_next_flag_alias = |arg: &str| arg.starts_with("-");
-        for (idx, alias_arg) in self.iter().enumerate() {
-            if aliases.contains(&alias_arg.as_str()) {
-                for (offset, arg) in self[idx + 1 ..].iter().enumerate() {
-                    if is_next_flag_alias(arg) {
-                        return Some(self[idx .. idx + 1 + offset].to_owned());
+/// Translate a shell glob into an equivalent (unanchored) regex body.
+///
+/// `**` matches any sequence of characters, `*` matches any run of
+/// non-`/` characters, `?` matches a single non-`/` character, and
+/// `[...]`/`[!...]` character classes pass through (with a leading `!`
+/// translated to `^`). Every other character is escaped. bare only ever
+/// matches against a single file name (never a path), so the `/`
+/// distinction is mostly moot here, but it's kept for forward
+/// compatibility should bare ever match against full paths.
+fn translate_glob(glob: &str) -> Result<String> {
+    let bytes = glob.as_bytes();
+    let mut out = String::new();
+    let mut idx = 0;
+    while idx < bytes.len() {
+        match bytes[idx] {
+            b'*' if bytes.get(idx + 1) == Some(&b'*') => {
+                out.push_str(".*");
+                idx += 2;
+            }
+            b'*' => {
+                out.push_str("[^/]*");
+                idx += 1;
+            }
+            b'?' => {
+                out.push_str("[^/]");
+                idx += 1;
+            }
+            b'[' => {
+                let start = idx + 1;
+                let mut end = start;
+                if bytes.get(end) == Some(&b'!') {
+                    end += 1;
+                }
+                if bytes.get(end) == Some(&b']') {
+                    end += 1;
+                }
+                while end < bytes.len() && bytes[end] != b']' {
+                    end += 1;
+                }
+                if end >= bytes.len() {
+                    return Err(Error::MalformedPattern(glob.to_string()));
+                }
+                out.push('[');
+                match glob[start..end].strip_prefix('!') {
+                    Some(rest) => {
+                        out.push('^');
+                        out.push_str(rest);
                     }
+                    None => out.push_str(&glob[start..end]),
                 }
-                return Some(self[idx .. self.len()].to_owned());
+                out.push(']');
+                idx = end + 1; // Slice off the closing ']'
+            }
+            _ => {
+                let ch = glob[idx..].chars().next().unwrap();
+                escape_glob_char(ch, &mut out);
+                idx += ch.len_utf8();
             }
         }
-        None
     }
+    Ok(out)
 }
 
-
-
-#[derive(Debug)]
-pub struct Args {
-    raw:              Vec<String>,
-    pub file_paths:   Vec<PathBuf>,
-    pub patterns:     Vec<Pattern>,
-    pub dry_run:      bool,
+/// Compile a glob pattern body into a case-insensitive, fully anchored
+/// regex equivalent to it.
+pub(crate) fn glob_to_regex(body: &str) -> Result<Regex> {
+    let translated = translate_glob(body)?;
+    Regex::new(&format!("^(?i){}$", translated))
+        .map_err(|e| Error::MalformedRegex(format!("{}", e)))
 }
 
-impl Args {
-    fn new() -> Self {
-        Args {
-            raw: env::args().collect(),
-            file_paths: vec![],
-            patterns:   vec![],
-            dry_run:    false,
-        }
-    }
-
-    fn parse_help(self, aliases: &[&str]) -> Self {
-        if self.raw.args_for(aliases).is_some() {
-            HelpWriter::new()
-                .text(
-"BARE is the ultimate BAtch REnaming tool. It works by matching regexes
-against file names, and applying them in the order they were provided.\nSee ")
-                .uri("https://doc.rust-lang.org/regex/regex/#syntax")
-                .text(" for regex syntax.\n\n")
-                .category("Usage:")
-                  .argument("  bare",  "[-h | --help]")
-                  .argument("      ",  "[-d | --dry-run]")
-                  .argument("      ",  "[-f FILE+ | --files FILE+]")
-                  .argument("      ",  "[-p [PAT REP]+ | --pattern [PAT REP]+]")
-                .text("\n")
-                .category("Options:")
-                  .option("  -h --help",    "Show this screen")
-                  .option("  -v --version", "Print the version number")
-                  .option("  -d --dry-run", "Don't actually rename any files")
-                  .option("  -f --files",   "Specify the files to rename")
-                  .option("  -p --pattern", "Match files ");
-            exit::quit();
-        }
-        self
+/// Compile a literal pattern body into a case-insensitive, fully
+/// anchored regex that matches it verbatim.
+fn literal_to_regex(body: &str) -> Result<Regex> {
+    let mut escaped = String::new();
+    for ch in body.chars() {
+        escape_glob_char(ch, &mut escaped);
     }
+    Regex::new(&format!("^(?i){}$", escaped))
+        .map_err(|e| Error::MalformedRegex(format!("{}", e)))
+}
 
-    fn parse_dry_run(mut self, aliases: &[&str]) -> Self {
-        self.dry_run = self.raw.args_for(aliases).is_some();
-        self
-    }
-
-    fn parse_version(self, aliases: &[&str]) -> Self {
-        if self.raw.args_for(aliases).is_some() {
-            HelpWriter::new()
-                .text("bare ")
-                .colored("v", color::BRIGHT_YELLOW)
-                .colored(env!("CARGO_PKG_VERSION"), color::BRIGHT_YELLOW)
-                .text("\n");
-            exit::quit();
-        }
-        self
-    }
-
-    fn parse_files(mut self, aliases: &[&str]) -> Self {
-        match self.raw.args_for(aliases) {
-            None => exit::abort(ExitCode::MissingRequiredCliArgument(
-                format!("{:?}", aliases))),
-            Some(args) => {
-                if args.len() == 1 && aliases.contains(&args[0].as_str()) {
-                    exit::abort(ExitCode::NotEnoughFiles);
-                }
-                for file in &args[1..] { // Slice off the alias
-                    self.file_paths.push(PathBuf::from(file));
-                }
-            },
-        };
-        self
+/// Compile a single `-p`/`--pattern` body (with its `syntax:` prefix
+/// already stripped and classified) into the regex bare will match with.
+fn compile_pattern(syntax: PatternSyntax, body: &str) -> Result<Regex> {
+    match syntax {
+        // Since the regexes are not used concurrently,
+        // the names won't clash with each other.
+        PatternSyntax::Regex => Regex::new(body)
+            .case_insensitive()
+            .named("regex")
+            .map_err(|e| Error::MalformedRegex(format!("{}", e))),
+        PatternSyntax::Glob => glob_to_regex(body),
+        PatternSyntax::Literal => literal_to_regex(body),
     }
+}
 
-    fn validate_patterns(raw_patterns: &[String], aliases: &[&str]) {
-        if !aliases.contains(&raw_patterns[0].as_str()) {
-            // TODO: Error: wrong format somehow
-        }
-        let patterns = &raw_patterns[1..];
-        let len = patterns.len();
-        if len < 2 {
-            exit::abort(ExitCode::NotEnoughPatterns(
-                format!("{:?}", &patterns)));
-        }
-        if len % 2 != 0 {
-            exit::abort(ExitCode::MalformedPattern(
-                format!("{:?}", &patterns)));
+/// Read rename rules from a pattern file, one `pattern<TAB>replacement`
+/// rule per line; blank lines and `#`-prefixed comments are ignored.
+/// Each pattern may carry the same `syntax:` prefix as a `-p` pattern,
+/// and likewise defaults to `glob:` when it carries none.
+/// Mirrors Mercurial's `parse_pattern_file_contents`.
+fn parse_pattern_file(path: &Path) -> Result<Vec<Pattern>> {
+    let contents = std::fs::read_to_string(path)?;
+    let mut patterns = vec![];
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
         }
+        let mut parts = line.splitn(2, '\t');
+        let raw_pattern = parts.next().unwrap_or("");
+        let replacement = parts.next()
+            .ok_or_else(|| Error::MalformedPattern(line.to_string()))?;
+        let (syntax, body) = PatternSyntax::parse(raw_pattern);
+        let regex = compile_pattern(syntax, body)?;
+        patterns.push(Pattern { regex, replacement: replacement.to_string() });
     }
-
-    fn parse_patterns(mut self, aliases: &[&str]) -> Self {
-        match self.raw.args_for(&aliases) {
-            None => exit::abort(ExitCode::MissingRequiredCliArgument(
-                format!("{:?}", aliases))),
-            Some(patterns) => {
-                Self::validate_patterns(&patterns, aliases);
-                let patterns = &patterns[1..]; // Slice off the alias proper
-                let mut idx = 0;
-                while idx < patterns.len() {
-                    // Since the regexes are not used concurrently,
-                    // the names won't clash with each other.
-                    let result = Regex::new(&patterns[idx])
-                        .case_insensitive()
-                        .named("regex");
-                    match result {
-                        Ok(regex) => {
-                            let replacement = patterns[idx + 1].to_string();
-                            self.patterns.push( (regex, replacement) );
-                            idx += 2;
-                        },
-                        Err(e) => {
-                            let msg = format!("{}", e);
-                            exit::abort(ExitCode::MalformedRegex(msg));
-                        },
-                    };
-                }
-            }
-        }
-        self
-    }
-
-    pub fn parse() -> Self {
-        Args::new()
-            .parse_help(    &["-h", "--help"])
-            .parse_dry_run( &["-d", "--dry-run"])
-            .parse_version( &["-v", "--version"])
-            .parse_files(   &["-f", "--files"])
-            .parse_patterns(&["-p", "--pattern"])
-    }
+    Ok(patterns)
 }
 
-
-
-
-struct HelpWriter {
-    writer: Writer
+/// The sentinel that, in place of a real file name, means "read the
+/// file list from stdin instead".
+const STDIN_SENTINEL: &str = "-";
+
+/// Split a newline- (or, with `null_delimited`, NUL-) delimited blob of
+/// paths, discarding blank entries and trailing `\r`s.
+fn split_paths(input: &str, null_delimited: bool) -> Vec<PathBuf> {
+    let separator = if null_delimited { '\0' } else { '\n' };
+    input
+        .split(separator)
+        .map(|s| s.trim_end_matches('\r'))
+        .filter(|s| !s.is_empty())
+        .map(PathBuf::from)
+        .collect()
 }
 
-impl HelpWriter {
-    pub fn new() -> Self { HelpWriter {  writer: Writer::new()  } }
-
-    pub fn category(mut self, cat: &str) -> Self {
-        self.writer.writeln_color(cat, color::YELLOW).unwrap();
-        self
-    }
+/// Read a newline- (or, with `null_delimited`, NUL-) delimited list of
+/// paths from stdin. Mirrors the `-f -`/`--files-from -` conventions
+/// other CLI tools use to compose with `find`, `fd`, or `rg -l`.
+fn read_paths_from_stdin(null_delimited: bool) -> Result<Vec<PathBuf>> {
+    let mut input = String::new();
+    io::stdin().read_to_string(&mut input)?;
+    Ok(split_paths(&input, null_delimited))
+}
 
-    pub fn argument(mut self, prefix: &str, arg: &str) -> Self {
-        let p = String::from(prefix) + " ";
-        self.writer.write_color(&p, color::GREEN).unwrap();
-        self.writer.writeln_color(arg, color::CYAN).unwrap();
-        self
-    }
+/// Raw command-line arguments, parsed directly out of `env::args()` via
+/// clap. See [`TypedCliArgs`] for the validated, ready-to-use form bare's
+/// core actually operates on.
+#[derive(Debug, Clone, clap::Parser)]
+#[command(
+    name = "bare",
+    version,
+    about = "BARE is the ultimate BAtch REnaming tool. It works by \
+              matching patterns against file names, and applying them \
+              in the order they were provided."
+)]
+pub struct CliArgs {
+    /// Don't actually rename any files; only print what would happen.
+    #[arg(short, long)]
+    pub dry_run: bool,
+
+    /// Lower-case destination file names before applying patterns.
+    #[arg(short, long)]
+    pub lower_case: bool,
+
+    /// Upper-case destination file names before applying patterns.
+    #[arg(short, long)]
+    pub upper_case: bool,
+
+    /// The files to rename. Pass `-` to read the list from stdin
+    /// instead (see also `--files-from`).
+    #[arg(short, long = "files", num_args = 1..)]
+    pub files: Vec<PathBuf>,
+
+    /// Read the file list from stdin; equivalent to `--files -`.
+    #[arg(long)]
+    pub files_from: bool,
+
+    /// When reading the file list from stdin, split on NUL bytes
+    /// instead of newlines, to safely handle names containing newlines.
+    #[arg(short = '0', long)]
+    pub null: bool,
+
+    /// Apply renames even if they collide: multiple sources mapping to
+    /// the same destination, or a destination that already exists.
+    #[arg(long)]
+    pub force: bool,
+
+    /// `(pattern, replacement)` pairs, applied to each file name in the
+    /// order given. A pattern may carry a `re:`, `glob:`, or `lit:`
+    /// syntax prefix; it defaults to `glob:` when no prefix is given.
+    #[arg(short, long = "pattern", num_args = 2..)]
+    pub patterns: Vec<String>,
+
+    /// Only consider files whose name matches one of these globs. When
+    /// none are given, every file passed via `--files` is a candidate.
+    #[arg(long)]
+    pub include: Vec<String>,
+
+    /// Exclude files whose name matches one of these globs, even if
+    /// they matched `--include` (or would be a candidate by default).
+    #[arg(long)]
+    pub exclude: Vec<String>,
+
+    /// Read additional `(pattern, replacement)` rules from this file,
+    /// one `pattern<TAB>replacement` rule per line (`#` comments and
+    /// blank lines ignored), with the same syntax prefixes as `-p`.
+    /// Appended after any `-p` rules, in the order they appear in the file.
+    #[arg(long)]
+    pub pattern_file: Option<PathBuf>,
+}
 
-    pub fn option(mut self, left: &str, right: &str) -> Self {
-        let left = &format!("{:<15} ", left);
-        let right = &format!("{} ", right);
-        self.writer.write_color(left, color::BRIGHT_WHITE).unwrap();
-        self.writer.writeln_color(right, color::WHITE).unwrap();
-        self
-    }
+/// Validated, ready-to-use CLI arguments: `--pattern` bodies have been
+/// compiled into [`Pattern`]s and every invariant [`CliArgs`] can't
+/// enforce on its own (e.g. an even number of pattern/replacement
+/// arguments) has been checked.
+#[derive(Debug, Clone)]
+pub struct TypedCliArgs {
+    pub dry_run: bool,
+    pub files: Vec<PathBuf>,
+    pub patterns: Vec<Pattern>,
+    pub lower_case: bool,
+    pub upper_case: bool,
+    pub include: Vec<Regex>,
+    pub exclude: Vec<Regex>,
+    pub force: bool,
+}
 
-    pub fn uri(mut self, uri: &str) -> Self {
-        self.writer.write_color(uri, color::MAGENTA).unwrap();
-        self
-    }
+impl TryFrom<CliArgs> for TypedCliArgs {
+    type Error = Error;
 
-    pub fn colored(mut self, text: &str, color: color::Color) -> Self {
-        self.writer.write_color(text, color).unwrap();
-        self
-    }
+    fn try_from(args: CliArgs) -> Result<Self> {
+        if args.patterns.len() % 2 != 0 {
+            return Err(Error::MalformedPattern(format!("{:?}", args.patterns)));
+        }
+        let mut patterns = vec![];
+        let mut idx = 0;
+        while idx < args.patterns.len() {
+            let (syntax, body) = PatternSyntax::parse(&args.patterns[idx]);
+            let regex = compile_pattern(syntax, body)?;
+            let replacement = args.patterns[idx + 1].clone();
+            patterns.push(Pattern { regex, replacement });
+            idx += 2;
+        }
+        if let Some(path) = &args.pattern_file {
+            patterns.extend(parse_pattern_file(path)?);
+        }
+        if patterns.is_empty() {
+            return Err(Error::NotEnoughPatterns(format!("{:?}", args.patterns)));
+        }
+        let include = args.include.iter().map(|g| glob_to_regex(g)).collect::<Result<_>>()?;
+        let exclude = args.exclude.iter().map(|g| glob_to_regex(g)).collect::<Result<_>>()?;
+
+        let reads_from_stdin = args.files_from
+            || args.files.iter().any(|f| f.as_os_str() == STDIN_SENTINEL);
+        let mut files: Vec<PathBuf> = args
+            .files
+            .into_iter()
+            .filter(|f| f.as_os_str() != STDIN_SENTINEL)
+            .collect();
+        if reads_from_stdin {
+            files.extend(read_paths_from_stdin(args.null)?);
+        }
+        if files.is_empty() {
+            return Err(Error::NotEnoughFiles);
+        }
 
-    pub fn text(mut self, text: &str) -> Self {
-        self.writer.write(text).unwrap();
-        self
+        Ok(TypedCliArgs {
+            dry_run: args.dry_run,
+            files,
+            patterns,
+            lower_case: args.lower_case,
+            upper_case: args.upper_case,
+            include,
+            exclude,
+            force: args.force,
+        })
     }
 }
 
 /// Print a question, then wait for user input.
 /// Keep asking the question while the user input fails validation.
 /// Return the answer upon successful validation.
-pub fn ask_user(question: &str, validator: &Regex) -> String {
+pub fn ask_user(question: &str, validator: &Regex) -> io::Result<String> {
     let mut log = RainbowLog::new();
     let mut answer = String::new();
     while !validator.is_match(&answer) {
-        log.info(&format!("{}", question));
-        io::stdout().flush().unwrap_or_else(
-            |e| log.error(&format!("Error flushing stdout: {:?}", e)) );
+        log.info(&format!("{}", question))?;
+        io::stdout().flush()?;
         answer.clear();
-        io::stdin().read_line(&mut answer).expect("Failed to read input");
+        io::stdin().read_line(&mut answer)?;
     }
-    answer
+    Ok(answer)
 }
 
 
 
 
-
 #[cfg(test)]
 mod tests {
-    use crate::bare::cli::ArgsFor;
-
-    fn raw_args() -> Vec<String> {
-        to_string_vec(&vec![
-            // Do *NOT* alter the args as they are.
-            // They are mined 'by position' below.
-            "bare",                                 // program name
-            "-p", "ein", "zwei", "drei", "vier",    // patterns
-            "--files", "foo.bar", "baz.qux",        // files
-            "-d",                                   // dry run
-            "--help",                               // help
-            "--version",                            // version
-            // ... append more here
-        ])
+    use crate::bare::cli::{
+        parse_pattern_file, split_paths, translate_glob, CliArgs, PatternSyntax, TypedCliArgs,
+    };
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn bare_args() -> CliArgs {
+        CliArgs {
+            dry_run: false,
+            lower_case: false,
+            upper_case: false,
+            files: vec![PathBuf::from("/tmp/bare_test/shooshoo.bar")],
+            files_from: false,
+            null: false,
+            force: false,
+            patterns: vec![],
+            include: vec![],
+            exclude: vec![],
+            pattern_file: None,
+        }
     }
 
-    fn to_string_vec(v: &[&str]) -> Vec<String> {
-        let mut r: Vec<String> = vec![];
-        for s in v.iter() {
-            r.push(s.to_string());
-        }
-        r
+    #[test]
+    fn pattern_syntax_defaults_to_glob() {
+        assert_eq!(PatternSyntax::parse("*.jpeg"), (PatternSyntax::Glob, "*.jpeg"));
     }
 
-    fn subvec(v: Vec<String>, start: usize, end: usize) -> Vec<String> {
-        let mut r: Vec<String> = vec![];
-        for i in start .. end {
-            r.push(v[i].clone());
-        }
-        r
+    #[test]
+    fn pattern_syntax_honors_prefix() {
+        assert_eq!(PatternSyntax::parse("re:^a.*z$"), (PatternSyntax::Regex, "^a.*z$"));
+        assert_eq!(PatternSyntax::parse("glob:*.jpeg"), (PatternSyntax::Glob, "*.jpeg"));
+        assert_eq!(PatternSyntax::parse("lit:foo.bar"), (PatternSyntax::Literal, "foo.bar"));
+    }
+
+    #[test]
+    fn translate_glob_handles_wildcards_and_classes() {
+        assert_eq!(translate_glob("*.jpeg").unwrap(), "[^/]*\\.jpeg");
+        assert_eq!(translate_glob("**.jpeg").unwrap(), ".*\\.jpeg");
+        assert_eq!(translate_glob("img?.jpeg").unwrap(), "img[^/]\\.jpeg");
+        assert_eq!(translate_glob("[abc].jpeg").unwrap(), "[abc]\\.jpeg");
+        assert_eq!(translate_glob("[!abc].jpeg").unwrap(), "[^abc]\\.jpeg");
+    }
+
+    #[test]
+    fn translate_glob_rejects_unterminated_class() {
+        assert!(translate_glob("[abc.jpeg").is_err());
     }
 
     #[test]
-    fn test_args_for_help() {
-        let raw = raw_args();
-        let hargs = raw.args_for(&["-h", "--help"]).unwrap();
-        assert_eq!(hargs.len(), 1);
-        assert_eq!(raw[10].to_string(),  hargs[0].to_string());
+    fn parse_pattern_file_skips_blanks_and_comments() {
+        let path = PathBuf::from("/tmp/bare_test/patterns.txt");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "\n# a comment\nglob:*.jpeg\tbar\nre:^foo$\tbaz\n").unwrap();
+        let patterns = parse_pattern_file(&path).unwrap();
+        assert_eq!(patterns.len(), 2);
+        assert_eq!(patterns[0].replacement, "bar");
+        assert_eq!(patterns[1].replacement, "baz");
     }
 
     #[test]
-    fn test_args_for_dry_run() {
-        let raw = raw_args();
-        let dargs = raw.args_for(&["-d", "--dry-run"]).unwrap();
-        assert_eq!(dargs.len(), 1);
-        assert_eq!(raw[9].to_string(),  dargs[0].to_string());
+    fn parse_pattern_file_rejects_lines_without_a_tab() {
+        let path = PathBuf::from("/tmp/bare_test/malformed_patterns.txt");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "glob:*.jpeg\n").unwrap();
+        assert!(parse_pattern_file(&path).is_err());
     }
 
     #[test]
-    fn test_args_for_version() {
-        let raw = raw_args();
-        let vargs = raw.args_for(&["-v", "--version"]).unwrap();
-        assert_eq!(vargs.len(), 1);
-        assert_eq!(raw[11].to_string(),  vargs[0].to_string());
+    fn split_paths_newline_delimited() {
+        let paths = split_paths("foo.bar\nbaz.qux\r\n\n", false);
+        assert_eq!(paths, vec![PathBuf::from("foo.bar"), PathBuf::from("baz.qux")]);
     }
 
     #[test]
-    fn test_args_for_patterns() {
-        let (raw, start, end) = (raw_args(), 1, 6);
-        let pargs = raw.args_for(&["-p", "--pattern"]).unwrap();
-        assert_eq!(subvec(raw, start, end),  pargs);
+    fn split_paths_null_delimited() {
+        let paths = split_paths("foo.bar\0baz.qux\0", true);
+        assert_eq!(paths, vec![PathBuf::from("foo.bar"), PathBuf::from("baz.qux")]);
     }
 
     #[test]
-    fn test_args_for_files() {
-        let (raw, start, end) = (raw_args(), 6, 9);
-        let fargs = raw.args_for(&["-f", "--files"]).unwrap();
-        assert_eq!(subvec(raw, start, end),  fargs);
+    fn try_from_rejects_when_no_patterns_given() {
+        let args = bare_args();
+        assert!(TypedCliArgs::try_from(args).is_err());
     }
 
     #[test]
-    fn test_args_for_bogus_flag() {
-        let raw = raw_args();
-        let no_args = raw.args_for(&["-s", "--some-bogus-flag"]);
-        assert_eq!(None,  no_args);
+    fn files_from_rejects_a_value_since_the_only_meaningful_one_is_the_flag_itself() {
+        use clap::Parser;
+        let result = CliArgs::try_parse_from([
+            "bare", "--files-from", "mylist.txt", "-p", "*.jpeg", "bar",
+        ]);
+        assert!(result.is_err());
     }
 }
@@ -4,8 +4,18 @@ pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Debug, thiserror::Error, displaydoc::Display)]
 pub enum Error {
-    /// I/O error: {0}
+    /// I/O error: {0:?}
     IoError(ioe::IoError),
+    /// malformed pattern(s): {0}
+    MalformedPattern(String),
+    /// malformed regex: {0}
+    MalformedRegex(String),
+    /// provide at least 1 file
+    NotEnoughFiles,
+    /// not enough pattern(s) in {0}
+    NotEnoughPatterns(String),
+    /// refusing to rename: {0:?} (pass --force to override)
+    RenameCollision(Vec<crate::bare::Collision>),
 }
 
 impl From<std::io::Error> for Error {
@@ -1,57 +1,25 @@
-//! Exiting the program made trivial.
+//! Mapping between library [`Error`]s and OS process exit codes.
+//!
+//! The actual `process::exit` call lives in `main`, in exactly one
+//! place; this module only decides *which* code to pass it.
 
-use crate::bare::error::Result;
-use std::io;
-use std::io::Write;
-use std::process;
+use crate::bare::error::Error;
 
-/// Exit codes for the program.
-#[derive(Debug, Clone)]
-pub enum ExitCode {
-    Ok,
-    MalformedPattern(String),
-    MalformedRegex(String),
-    MissingRequiredCliArgument(String),
-    NotEnoughFiles,
-    NotEnoughPatterns(String),
-}
-
-fn exit(exit_code: ExitCode) -> Result<()> {
-    io::stdout().flush()?;
-    process::exit(match exit_code {
-        ExitCode::Ok => 0,
-        ExitCode::MalformedPattern(ref patterns) => {
-            println!("malformed pattern(s): {}", patterns);
-            1
-        }
-        ExitCode::MalformedRegex(ref patterns) => {
-            println!("malformed regex: {}", patterns);
-            2
-        }
-        ExitCode::MissingRequiredCliArgument(ref patterns) => {
-            println!("Need to provide one of {}", patterns);
-            3
-        }
-        ExitCode::NotEnoughFiles => {
-            println!("provide at least 1 file");
-            4
-        }
-        ExitCode::NotEnoughPatterns(ref patterns) => {
-            println!("not enough pattern(s) in {}", patterns);
-            5
-        }
-    });
-}
-
-/// Abnormally exit the program. The `exit_code` value specifies the reason.
-pub fn abort(exit_code: ExitCode) -> Result<()> {
-    print!("Aborting, ");
-    exit(exit_code)?;
-    Ok(())
-}
-
-/// Normally exit the program.
-pub fn quit() -> Result<()> {
-    exit(ExitCode::Ok)?;
-    Ok(())
+/// The process exit code for each [`Error`] variant. `MalformedPattern`,
+/// `MalformedRegex`, `NotEnoughFiles` and `NotEnoughPatterns` keep the
+/// codes the old `ExitCode` enum used historically for them; 3 (that
+/// enum's `MissingRequiredCliArgument`) is retired, since clap now
+/// validates required arguments itself and no code path constructs the
+/// equivalent `Error` variant anymore. `IoError` and `RenameCollision`
+/// didn't exist back then, so they get fresh codes (6 and 7) that
+/// don't collide with the historical ones.
+pub fn code_for(error: &Error) -> i32 {
+    match error {
+        Error::MalformedPattern(_) => 1,
+        Error::MalformedRegex(_) => 2,
+        Error::NotEnoughFiles => 4,
+        Error::NotEnoughPatterns(_) => 5,
+        Error::IoError(_) => 6,
+        Error::RenameCollision(_) => 7,
+    }
 }
@@ -0,0 +1,111 @@
+//! Matchers that narrow down which files bare considers eligible for
+//! renaming, independent of the rewrite patterns themselves. Mirrors
+//! Mercurial's include/exclude/difference matchers.
+
+use regex::Regex;
+
+pub(crate) trait Matcher {
+    /// Whether `name` is matched.
+    fn matches(&self, name: &str) -> bool;
+}
+
+/// Matches a file name against the union of a set of patterns. An
+/// `IncludeMatcher` built from an empty set matches everything, i.e.
+/// "no `--include` given" means "every file is a candidate".
+pub(crate) struct IncludeMatcher<'a> {
+    patterns: &'a [Regex],
+}
+
+impl<'a> IncludeMatcher<'a> {
+    pub(crate) fn new(patterns: &'a [Regex]) -> Self {
+        IncludeMatcher { patterns }
+    }
+}
+
+impl Matcher for IncludeMatcher<'_> {
+    fn matches(&self, name: &str) -> bool {
+        self.patterns.is_empty() || self.patterns.iter().any(|p| p.is_match(name))
+    }
+}
+
+/// Matches a file name against the union of a set of patterns, with
+/// the opposite empty-set behavior from [`IncludeMatcher`]: an
+/// `ExcludeMatcher` built from an empty set matches nothing, i.e.
+/// "no `--exclude` given" means "no file is excluded".
+pub(crate) struct ExcludeMatcher<'a> {
+    patterns: &'a [Regex],
+}
+
+impl<'a> ExcludeMatcher<'a> {
+    pub(crate) fn new(patterns: &'a [Regex]) -> Self {
+        ExcludeMatcher { patterns }
+    }
+}
+
+impl Matcher for ExcludeMatcher<'_> {
+    fn matches(&self, name: &str) -> bool {
+        self.patterns.iter().any(|p| p.is_match(name))
+    }
+}
+
+/// Matches everything `include` matches, minus everything `exclude`
+/// matches.
+pub(crate) struct DifferenceMatcher<A, B> {
+    include: A,
+    exclude: B,
+}
+
+impl<A: Matcher, B: Matcher> DifferenceMatcher<A, B> {
+    pub(crate) fn new(include: A, exclude: B) -> Self {
+        DifferenceMatcher { include, exclude }
+    }
+}
+
+impl<A: Matcher, B: Matcher> Matcher for DifferenceMatcher<A, B> {
+    fn matches(&self, name: &str) -> bool {
+        self.include.matches(name) && !self.exclude.matches(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn regex(literal: &str) -> Regex {
+        Regex::new(literal).unwrap()
+    }
+
+    #[test]
+    fn include_matcher_matches_everything_when_empty() {
+        let matcher = IncludeMatcher::new(&[]);
+        assert!(matcher.matches("anything.png"));
+    }
+
+    #[test]
+    fn include_matcher_matches_union_of_patterns() {
+        let patterns = vec![regex("^.*\\.png$"), regex("^.*\\.jpg$")];
+        let matcher = IncludeMatcher::new(&patterns);
+        assert!(matcher.matches("foo.png"));
+        assert!(matcher.matches("foo.jpg"));
+        assert!(!matcher.matches("foo.gif"));
+    }
+
+    #[test]
+    fn difference_matcher_subtracts_exclude_from_include() {
+        let include_patterns = [regex("^.*\\.png$")];
+        let exclude_patterns = [regex("^.*_thumb\\.png$")];
+        let include = IncludeMatcher::new(&include_patterns);
+        let exclude = ExcludeMatcher::new(&exclude_patterns);
+        let matcher = DifferenceMatcher::new(include, exclude);
+        assert!(matcher.matches("foo.png"));
+        assert!(!matcher.matches("foo_thumb.png"));
+    }
+
+    #[test]
+    fn difference_matcher_keeps_everything_when_exclude_is_empty() {
+        let include = IncludeMatcher::new(&[]);
+        let exclude = ExcludeMatcher::new(&[]);
+        let matcher = DifferenceMatcher::new(include, exclude);
+        assert!(matcher.matches("anything.png"));
+    }
+}
@@ -5,10 +5,13 @@ pub mod cli;
 pub mod error;
 pub mod exit;
 pub mod log;
+pub(crate) mod matcher;
 
 use crate::bare::cli::TypedCliArgs;
+use crate::bare::error::{Error, Result};
+use crate::bare::matcher::{DifferenceMatcher, ExcludeMatcher, IncludeMatcher, Matcher};
 use regex::Regex;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 
 #[derive(Debug, Clone, clap::Parser)]
@@ -31,8 +34,120 @@ pub type Rename = (String, String);
 /// mapping a parent dir to multiple src -> dst renames.
 pub type Proposal = HashMap<PathBuf, Vec<Rename>>;
 
+/// A problem with a [`Proposal`] found before any renames are applied:
+/// either two sources renaming to the same destination, or a
+/// destination that already exists on disk and isn't itself being
+/// renamed away.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Collision {
+    /// Multiple sources within `parent` rename to the same `dst`.
+    DuplicateDestination {
+        parent: PathBuf,
+        dst: String,
+        srcs: Vec<String>,
+    },
+    /// `dst` already exists within `parent` and isn't itself a source
+    /// being renamed away, so applying the rename would clobber it.
+    ExistingDestination { parent: PathBuf, dst: String },
+}
+
+/// Find every [`Collision`] in `proposal`, i.e. every rename that
+/// `std::fs::rename` would silently let clobber something.
+pub(crate) fn find_collisions(proposal: &Proposal) -> Vec<Collision> {
+    let mut collisions = vec![];
+    for (parent, renames) in proposal.iter() {
+        let renamed: Vec<&Rename> = renames.iter().filter(|(src, dst)| src != dst).collect();
+
+        let mut srcs_by_dst: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (src, dst) in renamed.iter() {
+            srcs_by_dst.entry(dst.as_str()).or_default().push(src.as_str());
+        }
+        for (dst, srcs) in srcs_by_dst.into_iter() {
+            if srcs.len() > 1 {
+                collisions.push(Collision::DuplicateDestination {
+                    parent: parent.clone(),
+                    dst: dst.to_string(),
+                    srcs: srcs.into_iter().map(str::to_string).collect(),
+                });
+            }
+        }
+
+        let renamed_away: HashSet<&str> = renamed.iter().map(|(src, _)| src.as_str()).collect();
+        let mut existing_dsts: HashSet<&str> = HashSet::new();
+        for (_src, dst) in renamed.iter() {
+            if parent.join(dst).exists()
+                && !renamed_away.contains(dst.as_str())
+                && existing_dsts.insert(dst.as_str())
+            {
+                collisions.push(Collision::ExistingDestination {
+                    parent: parent.clone(),
+                    dst: dst.clone(),
+                });
+            }
+        }
+    }
+    collisions
+}
+
+/// The result of [`run`]: either a preview of the renames that would
+/// happen, or a record of the renames that were actually applied.
+#[derive(Debug)]
+pub enum Outcome {
+    /// Renames that were proposed, but not applied to disk.
+    Proposed {
+        proposal: Proposal,
+        not_found: Vec<PathBuf>,
+        collisions: Vec<Collision>,
+    },
+    /// Renames that were applied to disk. `failures` holds the source
+    /// path and error for any individual rename that didn't go through;
+    /// one bad rename doesn't stop the rest from being attempted.
+    Applied {
+        proposal: Proposal,
+        not_found: Vec<PathBuf>,
+        failures: Vec<(PathBuf, Error)>,
+    },
+}
+
+/// Drive bare's core flow: build a rename [`Proposal`] from `args`, and,
+/// when `apply` is `true`, carry it out on disk.
+///
+/// This is the embeddable entry point: it never prompts, never prints,
+/// and never calls `process::exit` — callers get a [`Result`] back and
+/// decide for themselves what to do with it. Unless `args.force` is
+/// set, applying a proposal with [`Collision`]s is refused outright, so
+/// batch runs don't quietly destroy data.
+pub fn run(args: TypedCliArgs, apply: bool) -> Result<Outcome> {
+    let (proposal, not_found) = propose_renames(&args);
+    let collisions = find_collisions(&proposal);
+    if !apply {
+        return Ok(Outcome::Proposed { proposal, not_found, collisions });
+    }
+    if !collisions.is_empty() && !args.force {
+        return Err(Error::RenameCollision(collisions));
+    }
+    let mut failures = vec![];
+    for (parent, renames) in proposal.iter() {
+        for (src_name, dst_name) in renames.iter() {
+            if src_name == dst_name {
+                continue;
+            }
+            let src = parent.join(src_name);
+            let dst = parent.join(dst_name);
+            if let Err(e) = std::fs::rename(&src, &dst) {
+                failures.push((src, Error::from(e)));
+            }
+        }
+    }
+    Ok(Outcome::Applied { proposal, not_found, failures })
+}
+
 pub(crate) fn propose_renames(args: &TypedCliArgs) -> (Proposal, Vec<PathBuf>) {
     let (mut proposal, mut files_not_found) = (HashMap::new(), vec![]);
+    let eligible = DifferenceMatcher::new(
+        IncludeMatcher::new(&args.include),
+        ExcludeMatcher::new(&args.exclude),
+    );
     for src_path in args.files.iter() {
         if !src_path.exists() {
             files_not_found.push(src_path.to_path_buf());
@@ -42,7 +157,15 @@ pub(crate) fn propose_renames(args: &TypedCliArgs) -> (Proposal, Vec<PathBuf>) {
             .file_name().unwrap(/*Option*/)
             .to_str().unwrap(/*Option*/)
             .to_string();
+        if !eligible.matches(&src_name) {
+            continue;
+        }
         let mut dst_name = src_name.clone();
+        if args.lower_case {
+            dst_name = dst_name.to_lowercase();
+        } else if args.upper_case {
+            dst_name = dst_name.to_uppercase();
+        }
         for Pattern { regex, replacement } in args.patterns.iter() {
             if regex.is_match(&dst_name) {
                 dst_name = regex
@@ -75,6 +198,9 @@ mod tests {
             patterns: patterns(),
             lower_case: false,
             upper_case: false,
+            include: vec![],
+            exclude: vec![],
+            force: false,
         };
         ensure_exist(&args.files);
         let (proposal, files_not_found) = bare::propose_renames(&args);
@@ -104,6 +230,9 @@ mod tests {
             }],
             lower_case: false,
             upper_case: false,
+            include: vec![],
+            exclude: vec![],
+            force: false,
         };
         ensure_dont_exist(&args.files);
         let (proposal, files_not_found) = bare::propose_renames(&args);
@@ -118,6 +247,60 @@ mod tests {
         );
     }
 
+    #[test]
+    fn find_collisions_detects_duplicate_destination() {
+        let mut proposal = HashMap::new();
+        proposal.insert(
+            PathBuf::from("/tmp/bare_test/"),
+            vec![
+                ("a.bar".to_string(), "c.bar".to_string()),
+                ("b.bar".to_string(), "c.bar".to_string()),
+            ],
+        );
+        let collisions = bare::find_collisions(&proposal);
+        assert_eq!(
+            collisions,
+            vec![bare::Collision::DuplicateDestination {
+                parent: PathBuf::from("/tmp/bare_test/"),
+                dst: "c.bar".to_string(),
+                srcs: vec!["a.bar".to_string(), "b.bar".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn find_collisions_detects_existing_destination() {
+        let dir = PathBuf::from("/tmp/bare_test_collision/");
+        fs::create_dir_all(&dir).unwrap();
+        File::create(dir.join("existing.bar")).unwrap();
+        let mut proposal = HashMap::new();
+        proposal.insert(
+            dir.clone(),
+            vec![("a.bar".to_string(), "existing.bar".to_string())],
+        );
+        let collisions = bare::find_collisions(&proposal);
+        assert_eq!(
+            collisions,
+            vec![bare::Collision::ExistingDestination {
+                parent: dir,
+                dst: "existing.bar".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn find_collisions_ignores_a_no_op_rename() {
+        let dir = PathBuf::from("/tmp/bare_test_collision/");
+        fs::create_dir_all(&dir).unwrap();
+        File::create(dir.join("unchanged.bar")).unwrap();
+        let mut proposal = HashMap::new();
+        proposal.insert(
+            dir,
+            vec![("unchanged.bar".to_string(), "unchanged.bar".to_string())],
+        );
+        assert_eq!(bare::find_collisions(&proposal), vec![]);
+    }
+
     #[cfg(unix)]
     fn paths() -> Vec<PathBuf> {
         vec![
@@ -3,19 +3,25 @@
 //! Copyright @ 2016-2022 Joey Ezechiels
 
 use crate::bare::{
-    cli::{self, CliArgs, TypedCliArgs},
+    cli::{CliArgs, TypedCliArgs},
     error::Result,
     exit,
     log::RainbowLog,
+    Outcome,
 };
 use clap::Parser;
 use regex::Regex;
+use std::process;
 
 pub mod bare;
 
 const DEFAULT_ANSWER: &'static str = "";
 
-fn main() -> Result<()> {
+/// Parse arguments, propose renames, ask for confirmation unless
+/// `--dry-run` was given, and apply them. The only place in the
+/// program that turns a [`Result`] into a process exit code is `main`;
+/// everything above this is free to use `?` and return early.
+fn drive() -> Result<Outcome> {
     let mut log = RainbowLog::new();
     #[allow(unused)]
     macro_rules! error {
@@ -42,8 +48,12 @@ fn main() -> Result<()> {
         };
     }
 
-    let args: TypedCliArgs = CliArgs::parse().into();
-    let (proposal, not_found) = crate::bare::propose_renames(&args);
+    let args: TypedCliArgs = TypedCliArgs::try_from(CliArgs::parse())?;
+    let preview = crate::bare::run(args.clone(), /* apply = */ false)?;
+    let (proposal, not_found, collisions) = match &preview {
+        Outcome::Proposed { proposal, not_found, collisions } => (proposal, not_found, collisions),
+        Outcome::Applied { .. } => unreachable!("a preview never applies renames"),
+    };
     for file in not_found.iter() {
         warn!("Not found, skipping {:?}\n", file);
     }
@@ -57,26 +67,42 @@ fn main() -> Result<()> {
             }
         }
     }
+    for collision in collisions.iter() {
+        warn!("    Collision: {:?}\n", collision);
+    }
     if args.dry_run {
-        return Ok(());
+        return Ok(preview);
     }
     let validator = Regex::new(r"^(?i)(y|n|yes|no)?\n$").unwrap();
-    let answer = cli::ask_user("Accord the changes? [y/N] ", &validator)?;
+    let answer = crate::bare::cli::ask_user("Accord the changes? [y/N] ", &validator)?;
     match answer.to_lowercase().trim() {
         "y" | "yes" => {
-            for (parent, renames) in proposal.iter() {
-                for &(ref src_name, ref dst_name) in renames.iter() {
-                    let src = parent.join(src_name);
-                    let dst = parent.join(dst_name);
-                    if let Err(e) = std::fs::rename(&src, &dst) {
-                        error!("Couldn't rename {:?}: {:?}\n", src, e);
-                    }
+            let applied = crate::bare::run(args, /* apply = */ true)?;
+            if let Outcome::Applied { ref failures, .. } = applied {
+                for (src, e) in failures.iter() {
+                    error!("Couldn't rename {:?}: {:?}\n", src, e);
                 }
             }
             info!("Done.\n");
+            Ok(applied)
+        }
+        "n" | "no" | DEFAULT_ANSWER => {
+            info!("Aborted.\n");
+            Ok(preview)
+        }
+        ans => {
+            warn!("Don't know what to do with '{:?}'\n", ans);
+            Ok(preview)
+        }
+    }
+}
+
+fn main() {
+    match drive() {
+        Ok(_) => process::exit(0),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(exit::code_for(&e));
         }
-        "n" | "no" | DEFAULT_ANSWER => info!("Aborted.\n"),
-        ans => warn!("Don't know what to do with '{:?}'\n", ans),
     }
-    exit::quit()
 }